@@ -0,0 +1,108 @@
+use napi_sys::*;
+use std::ptr;
+
+use crate::napi::error::{check_status, return_status_if_false, NapiError};
+
+pub fn create_array_with_length(env: napi_env, length: usize) -> Result<napi_value, NapiError> {
+    let mut array: napi_value = ptr::null_mut();
+
+    let status = unsafe { napi_create_array_with_length(env, length, &mut array) };
+    check_status(env, status)?;
+
+    Ok(array)
+}
+
+pub struct NapiArray {
+    pub env: napi_env,
+    pub array: napi_value,
+    pub current_index: u32,
+    pub length: u32,
+}
+
+impl NapiArray {
+    pub fn from_existing(env: napi_env, array: napi_value) -> Result<NapiArray, NapiError> {
+        return_status_if_false!(!array.is_null(), "array must not be null");
+
+        let mut length = 0;
+        let status = unsafe { napi_get_array_length(env, array, &mut length) };
+        check_status(env, status)?;
+
+        Ok(NapiArray {
+            env,
+            array,
+            length,
+            current_index: 0,
+        })
+    }
+
+    pub fn with_capacity(env: napi_env, capacity: usize) -> Result<NapiArray, NapiError> {
+        let array = create_array_with_length(env, capacity)?;
+        Ok(NapiArray {
+            env,
+            array,
+            length: 0,
+            current_index: 0,
+        })
+    }
+
+    /// Append `elem` at `self.length` via `napi_set_element`, then bump
+    /// `self.length`, rather than going through a reflected JS `push`.
+    pub fn push(&mut self, elem: napi_value) -> Result<(), NapiError> {
+        self.set(self.length, elem)?;
+        self.length += 1;
+
+        Ok(())
+    }
+
+    pub fn get(&self, index: u32) -> Result<napi_value, NapiError> {
+        let mut value: napi_value = ptr::null_mut();
+        let status = unsafe { napi_get_element(self.env, self.array, index, &mut value) };
+        check_status(self.env, status)?;
+
+        Ok(value)
+    }
+
+    pub fn set(&self, index: u32, value: napi_value) -> Result<(), NapiError> {
+        let status = unsafe { napi_set_element(self.env, self.array, index, value) };
+        check_status(self.env, status)
+    }
+
+    /// The array's current length, queried fresh via `napi_get_array_length`
+    /// rather than the possibly-stale `self.length` snapshot (e.g. after JS
+    /// code mutates the array out from under this handle).
+    pub fn len(&self) -> usize {
+        let mut length = self.length;
+        let status = unsafe { napi_get_array_length(self.env, self.array, &mut length) };
+
+        if check_status(self.env, status).is_err() {
+            return self.length as usize;
+        }
+
+        length as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Iterator for NapiArray {
+    type Item = Result<napi_value, NapiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_index >= self.length {
+            return None;
+        }
+
+        let value = self.get(self.current_index);
+        self.current_index += 1;
+
+        Some(value)
+    }
+}
+
+impl ExactSizeIterator for NapiArray {
+    fn len(&self) -> usize {
+        self.length as usize
+    }
+}
@@ -0,0 +1,94 @@
+use napi_sys::*;
+use std::os::raw::c_void;
+use std::ptr;
+
+use crate::napi::error::{check_status, NapiError};
+
+/// `napi_finalize` trampoline that reclaims the `Box<Vec<u8>>` handed to
+/// napi as the finalize hint, once V8 is done with the external buffer or
+/// arraybuffer backed by it.
+unsafe extern "C" fn finalize_vec(
+    _env: napi_env,
+    _finalize_data: *mut c_void,
+    finalize_hint: *mut c_void,
+) {
+    drop(Box::from_raw(finalize_hint as *mut Vec<u8>));
+}
+
+/// Create a JS `Buffer` backed directly by `data`, handing the existing
+/// allocation to V8 without copying. `data` is reclaimed when the JS
+/// buffer is garbage collected.
+pub fn create_external_buffer(env: napi_env, data: Vec<u8>) -> Result<napi_value, NapiError> {
+    let mut result: napi_value = ptr::null_mut();
+    let mut data = Box::new(data);
+    let ptr = data.as_mut_ptr();
+    let len = data.len();
+    let hint = Box::into_raw(data) as *mut c_void;
+
+    let status = unsafe {
+        napi_create_external_buffer(
+            env,
+            len,
+            ptr as *mut c_void,
+            Some(finalize_vec),
+            hint,
+            &mut result,
+        )
+    };
+
+    if let Err(e) = check_status(env, status) {
+        // napi didn't accept the allocation, so its finalizer will never
+        // run to reclaim it — reclaim it ourselves instead of leaking it.
+        drop(unsafe { Box::from_raw(hint as *mut Vec<u8>) });
+        return Err(e);
+    }
+
+    Ok(result)
+}
+
+/// Create a JS `ArrayBuffer` backed directly by `data`, handing the
+/// existing allocation to V8 without copying. `data` is reclaimed when the
+/// JS arraybuffer is garbage collected.
+pub fn create_external_arraybuffer(env: napi_env, data: Vec<u8>) -> Result<napi_value, NapiError> {
+    let mut result: napi_value = ptr::null_mut();
+    let mut data = Box::new(data);
+    let ptr = data.as_mut_ptr();
+    let len = data.len();
+    let hint = Box::into_raw(data) as *mut c_void;
+
+    let status = unsafe {
+        napi_create_external_arraybuffer(
+            env,
+            ptr as *mut c_void,
+            len,
+            Some(finalize_vec),
+            hint,
+            &mut result,
+        )
+    };
+
+    if let Err(e) = check_status(env, status) {
+        // napi didn't accept the allocation, so its finalizer will never
+        // run to reclaim it — reclaim it ourselves instead of leaking it.
+        drop(unsafe { Box::from_raw(hint as *mut Vec<u8>) });
+        return Err(e);
+    }
+
+    Ok(result)
+}
+
+/// Get the raw backing pointer and length of a JS `ArrayBuffer`, mirroring
+/// `get_buffer_info` for `Buffer`s.
+pub fn get_arraybuffer_info(
+    env: napi_env,
+    arraybuffer: napi_value,
+) -> Result<(*mut u8, usize), NapiError> {
+    let mut buff_size = 0;
+    let mut p_buff: *mut c_void = ptr::null_mut();
+
+    let status =
+        unsafe { napi_get_arraybuffer_info(env, arraybuffer, &mut p_buff, &mut buff_size) };
+    check_status(env, status)?;
+
+    Ok((p_buff as *mut u8, buff_size))
+}
@@ -0,0 +1,95 @@
+use napi_sys::*;
+use std::ffi::CStr;
+use std::ptr;
+
+/// An error produced by a failing napi call, or synthesized by one of our
+/// own wrappers without a failing napi call (an invalid argument, a value
+/// of the wrong JS type, a serde conversion error).
+#[derive(Debug)]
+pub enum NapiError {
+    Status {
+        status: napi_status,
+        message: String,
+        engine_error_code: u32,
+    },
+    Message(String),
+}
+
+impl std::fmt::Display for NapiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NapiError::Status { status, message, .. } => {
+                write!(f, "napi call failed with status {}: {}", status, message)
+            }
+            NapiError::Message(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+// Some integrations — e.g. serde's `ser::Error`/`de::Error` — require
+// `std::error::Error`. `failure::Fail` is not derived: failure's blanket
+// impl for `std::error::Error + Send + Sync + 'static` types provides it.
+impl std::error::Error for NapiError {}
+
+/// Check a `napi_status` returned from a raw napi call, turning any non-ok
+/// status into an `NapiError::Status` carrying the extended error info
+/// (message + engine error code) fetched via `napi_get_last_error_info`.
+pub fn check_status(env: napi_env, status: napi_status) -> Result<(), NapiError> {
+    if status == napi_status_napi_ok {
+        return Ok(());
+    }
+
+    let mut info: *const napi_extended_error_info = ptr::null();
+    let info_status = unsafe { napi_get_last_error_info(env, &mut info) };
+
+    let (message, engine_error_code) = if info_status == napi_status_napi_ok && !info.is_null() {
+        let info = unsafe { &*info };
+        let message = if info.error_message.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(info.error_message) }
+                .to_string_lossy()
+                .into_owned()
+        };
+        (message, info.engine_error_code)
+    } else {
+        (String::new(), 0)
+    };
+
+    Err(NapiError::Status {
+        status,
+        message,
+        engine_error_code,
+    })
+}
+
+/// Check a `napi_status` without touching `napi_env`, for the handful of
+/// napi APIs explicitly documented as callable off the JS thread (e.g.
+/// `napi_call_threadsafe_function`). `napi_get_last_error_info` (used by
+/// `check_status`) reads state owned by the JS thread's `napi_env` and is
+/// not safe to call from anywhere else, so the resulting error carries the
+/// raw status only, with no extended message or engine error code.
+pub fn check_status_threadsafe(status: napi_status) -> Result<(), NapiError> {
+    if status == napi_status_napi_ok {
+        return Ok(());
+    }
+
+    Err(NapiError::Status {
+        status,
+        message: String::new(),
+        engine_error_code: 0,
+    })
+}
+
+/// Return an `NapiError` if `condition` is false, without making a napi
+/// call of our own. Useful for validating arguments (null checks, type
+/// checks) before handing them to a napi function that would otherwise
+/// just segfault on bad input.
+macro_rules! return_status_if_false {
+    ($condition:expr, $message:expr) => {
+        if !($condition) {
+            return Err($crate::napi::error::NapiError::Message($message.to_string()));
+        }
+    };
+}
+pub(crate) use return_status_if_false;
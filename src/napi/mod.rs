@@ -3,85 +3,91 @@
 
 use napi_sys::*;
 use std::collections::BTreeMap;
-use std::debug_assert;
-use std::ffi::{CStr, CString};
+use std::ffi::CString;
 use std::os::raw::{c_char, c_void};
 use std::ptr;
 
 use failure::Error;
 
-#[derive(Debug, Fail)]
-enum NapiError {
-    #[fail(display = "unable to create string")]
-    UnableToCreateString,
-}
+pub mod array;
+pub mod buffer;
+pub mod error;
+pub mod object;
+pub mod reference;
+pub mod serde;
+pub mod threadsafe_function;
+
+pub use array::{create_array_with_length, NapiArray};
+
+use error::{check_status, return_status_if_false, NapiError};
 
 pub fn wrap_unsafe_create<T>(
     env: napi_env,
     item: T,
     f: unsafe extern "C" fn(napi_env, T, *mut napi_value) -> napi_status,
-) -> napi_value {
+) -> Result<napi_value, NapiError> {
     let mut result: napi_value = ptr::null_mut();
     let status = unsafe { f(env, item, &mut result) };
-    debug_assert!(status == napi_status_napi_ok);
-    result
+    check_status(env, status)?;
+
+    Ok(result)
 }
 
 pub fn wrap_unsafe_get<T: Default>(
     env: napi_env,
     value: napi_value,
     f: unsafe extern "C" fn(napi_env, napi_value, *mut T) -> napi_status,
-) -> T {
+) -> Result<T, NapiError> {
     let mut result: T = T::default();
     let status = unsafe { f(env, value, &mut result) };
-    debug_assert!(status == napi_status_napi_ok);
-    result
+    check_status(env, status)?;
+
+    Ok(result)
 }
 
-pub fn throw_error(env: napi_env, err: Error) {
+pub fn throw_error(env: napi_env, err: Error) -> Result<(), NapiError> {
     let msg = CString::new(err.to_string()).unwrap();
     let status = unsafe { napi_throw_error(env, ptr::null(), msg.as_ptr() as *const c_char) };
 
-    debug_assert!(status == napi_status_napi_ok)
+    check_status(env, status)
 }
 
-pub fn create_error(env: napi_env, err: Error) -> napi_value {
+pub fn create_error(env: napi_env, err: Error) -> Result<napi_value, NapiError> {
     let mut result: napi_value = ptr::null_mut();
-    let msg = create_string_utf8(env, &err.to_string());
+    let msg = create_string_utf8(env, &err.to_string())?;
 
-    //TODO
     let status = unsafe { napi_create_error(env, ptr::null_mut(), msg, &mut result) };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    result
+    Ok(result)
 }
 
-pub fn create_object(env: napi_env) -> napi_value {
+pub fn create_object(env: napi_env) -> Result<napi_value, NapiError> {
     let mut object: napi_value = ptr::null_mut();
 
     let status = unsafe { napi_create_object(env, &mut object) };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    object
+    Ok(object)
 }
 
-pub fn get_undefined_value(env: napi_env) -> napi_value {
+pub fn get_undefined_value(env: napi_env) -> Result<napi_value, NapiError> {
     let mut undefined_value: napi_value = ptr::null_mut();
     let status = unsafe { napi_get_undefined(env, &mut undefined_value) };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    undefined_value
+    Ok(undefined_value)
 }
 
-pub fn get_null_value(env: napi_env) -> napi_value {
+pub fn get_null_value(env: napi_env) -> Result<napi_value, NapiError> {
     let mut null_value: napi_value = ptr::null_mut();
     let status = unsafe { napi_get_null(env, &mut null_value) };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    null_value
+    Ok(null_value)
 }
 
-pub fn get_this(env: napi_env, info: napi_callback_info) -> napi_value {
+pub fn get_this(env: napi_env, info: napi_callback_info) -> Result<napi_value, NapiError> {
     let mut num_args = 0;
     let mut args: Vec<napi_value> = Vec::with_capacity(num_args);
     let mut this = ptr::null_mut();
@@ -97,11 +103,16 @@ pub fn get_this(env: napi_env, info: napi_callback_info) -> napi_value {
         )
     };
 
-    debug_assert!(status == napi_status_napi_ok);
-    this
+    check_status(env, status)?;
 
+    Ok(this)
 }
-pub fn get_arg(env: napi_env, info: napi_callback_info, arg_index: usize) -> napi_value {
+
+pub fn get_arg(
+    env: napi_env,
+    info: napi_callback_info,
+    arg_index: usize,
+) -> Result<napi_value, NapiError> {
     let mut num_args = arg_index + 1;
     let mut args: Vec<napi_value> = Vec::with_capacity(num_args);
 
@@ -116,32 +127,37 @@ pub fn get_arg(env: napi_env, info: napi_callback_info, arg_index: usize) -> nap
         )
     };
 
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
     unsafe { args.set_len(num_args) }
 
-    *args.get(arg_index).unwrap_or(&get_undefined_value(env))
+    match args.get(arg_index) {
+        Some(arg) => Ok(*arg),
+        None => get_undefined_value(env),
+    }
 }
 
-pub fn check_is_buffer(env: napi_env, value: napi_value) -> bool {
+pub fn check_is_buffer(env: napi_env, value: napi_value) -> Result<bool, NapiError> {
     let mut result = false;
     let status = unsafe { napi_is_buffer(env, value, &mut result) };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    result
+    Ok(result)
 }
 
-pub fn get_buffer_info(env: napi_env, buffer: napi_value) -> (*mut u8, usize) {
+pub fn get_buffer_info(env: napi_env, buffer: napi_value) -> Result<(*mut u8, usize), NapiError> {
+    return_status_if_false!(!buffer.is_null(), "buffer must not be null");
+
     let mut buff_size = 0;
     let mut p_buff: *mut c_void = ptr::null_mut();
 
     let status = unsafe { napi_get_buffer_info(env, buffer, &mut p_buff, &mut buff_size) };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    (p_buff as *mut u8, buff_size)
+    Ok((p_buff as *mut u8, buff_size))
 }
 
-pub fn create_buffer_copy(env: napi_env, slice: &[u8]) -> napi_value {
+pub fn create_buffer_copy(env: napi_env, slice: &[u8]) -> Result<napi_value, NapiError> {
     let mut _p_buff: *mut c_void = ptr::null_mut();
     let mut buffer: napi_value = ptr::null_mut();
 
@@ -155,104 +171,89 @@ pub fn create_buffer_copy(env: napi_env, slice: &[u8]) -> napi_value {
         )
     };
 
-    debug_assert!(status == napi_status_napi_ok);
-
-    buffer
-}
-
-pub fn create_array_with_length(env: napi_env, length: usize) -> napi_value {
-    let mut array: napi_value = ptr::null_mut();
+    check_status(env, status)?;
 
-    let status = unsafe { napi_create_array_with_length(env, length, &mut array) };
-    debug_assert!(status == napi_status_napi_ok);
-
-    array
+    Ok(buffer)
 }
 
-pub fn create_string_utf8(env: napi_env, string: &str) -> napi_value {
+pub fn create_string_utf8(env: napi_env, string: &str) -> Result<napi_value, NapiError> {
     let mut result: napi_value = ptr::null_mut();
     let p_str: *const c_char = string.as_ptr() as *const c_char;
 
     let status = unsafe { napi_create_string_utf8(env, p_str, string.len(), &mut result) };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    result
+    Ok(result)
 }
 
-pub fn get_string(env: napi_env, value: napi_value) -> Result<String, Error> {
-    let mut string_length_value = ptr::null_mut();
-
-    let length_value = create_string_utf8(env, &"length");
+pub fn get_string(env: napi_env, value: napi_value) -> Result<String, NapiError> {
+    let value_type = get_typeof(env, value)?;
+    return_status_if_false!(
+        value_type == napi_valuetype_napi_string,
+        "expected a string"
+    );
 
-    let status = unsafe { napi_get_property(env, value, length_value, &mut string_length_value) };
-
-    if status != napi_status_napi_ok {
-        bail!(NapiError::UnableToCreateString)
-    }
+    // First call with a null buffer to learn the exact UTF-8 byte length,
+    // then allocate and fill it. The JS "length" property is a UTF-16
+    // code-unit count and can't be used to size a UTF-8 byte buffer.
+    let mut utf8_len = 0;
+    let status =
+        unsafe { napi_get_value_string_utf8(env, value, ptr::null_mut(), 0, &mut utf8_len) };
+    check_status(env, status)?;
 
-    let mut num: u32 = 0;
-    let status = unsafe { napi_get_value_uint32(env, string_length_value, &mut num) };
-    debug_assert!(status == napi_status_napi_ok);
-
-    num += 1; //allow for null terminating c string
-
-    let mut vec: Vec<u8> = Vec::with_capacity(num as usize);
-    let cstr = unsafe { CStr::from_ptr(vec.as_ptr() as *const c_char) };
-    let mut length = 0;
+    let mut vec: Vec<u8> = Vec::with_capacity(utf8_len + 1);
+    let mut written = 0;
 
     let status = unsafe {
         napi_get_value_string_utf8(
             env,
             value,
-            cstr.as_ptr() as *mut c_char,
-            num as usize,
-            &mut length,
+            vec.as_mut_ptr() as *mut c_char,
+            utf8_len + 1,
+            &mut written,
         )
     };
-    if status == napi_status_napi_string_expected {
-        bail!(NapiError::UnableToCreateString)
-    }
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    unsafe { vec.set_len(length) }
+    unsafe { vec.set_len(written) }
 
-    String::from_utf8(vec).or(Err(NapiError::UnableToCreateString.into()))
+    String::from_utf8(vec).or_else(|e| Err(NapiError::Message(e.to_string())))
 }
 
-pub fn create_buffer(env: napi_env, len: usize) -> napi_value {
+pub fn create_buffer(env: napi_env, len: usize) -> Result<napi_value, NapiError> {
     let mut _p_buff: *mut c_void = ptr::null_mut();
     let mut buffer: napi_value = ptr::null_mut();
 
     let status = unsafe { napi_create_buffer(env, len, &mut _p_buff, &mut buffer) };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    buffer
+    Ok(buffer)
 }
 
-pub fn create_reference(env: napi_env, value: napi_value) -> napi_ref {
+pub fn create_reference(env: napi_env, value: napi_value) -> Result<napi_ref, NapiError> {
     let mut reference: napi_ref = ptr::null_mut();
 
     let status = unsafe { napi_create_reference(env, value, 1, &mut reference) };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    reference
+    Ok(reference)
 }
 
-pub fn get_reference_value(env: napi_env, reference: napi_ref) -> napi_value {
+pub fn get_reference_value(env: napi_env, reference: napi_ref) -> Result<napi_value, NapiError> {
     let mut value: napi_value = ptr::null_mut();
 
     let status = unsafe { napi_get_reference_value(env, reference, &mut value) };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    value
+    Ok(value)
 }
 
-pub fn delete_reference(env: napi_env, reference: napi_ref) {
+pub fn delete_reference(env: napi_env, reference: napi_ref) -> Result<(), NapiError> {
     let status = unsafe { napi_delete_reference(env, reference) };
-    debug_assert!(status == napi_status_napi_ok)
+    check_status(env, status)
 }
 
-pub fn create_int32(env: napi_env, num: i32) -> napi_value {
+pub fn create_int32(env: napi_env, num: i32) -> Result<napi_value, NapiError> {
     wrap_unsafe_create(env, num, napi_create_int32)
 }
 
@@ -260,132 +261,55 @@ pub struct NapiEnv {
     pub env: napi_env,
 }
 
-pub fn get_typeof(env: napi_env, value: napi_value) -> napi_valuetype {
+pub fn get_typeof(env: napi_env, value: napi_value) -> Result<napi_valuetype, NapiError> {
     let mut result = 0;
     let status = unsafe { napi_typeof(env, value, &mut result) };
-    debug_assert!(status == napi_status_napi_ok);
-
-    result
-}
-
-pub struct NapiArray {
-    pub env: napi_env,
-    pub array: napi_value,
-    pub current_index: u32,
-    pub length: u32,
-}
-
-impl NapiArray {
-    pub fn from_existing(env: napi_env, array: napi_value) -> NapiArray {
-        let mut length = 0;
-        let status = unsafe { napi_get_array_length(env, array, &mut length) };
-        debug_assert!(status == napi_status_napi_ok);
-
-        NapiArray {
-            env,
-            array,
-            length,
-            current_index: 0,
-        }
-    }
-    pub fn with_capacity(env: napi_env, capacity: usize) -> NapiArray {
-        let array = create_array_with_length(env, capacity);
-        NapiArray {
-            env,
-            array,
-            length: 0,
-            current_index: 0,
-        }
-    }
-
-    pub fn push(&mut self, elem: napi_value) {
-        //TODO: the push function (in push_array) could be stored in this object instead of having to get it for
-        //every call to push_array.
-        push_array(self.env, self.array, elem)
-    }
-}
-
-impl Iterator for NapiArray {
-    type Item = napi_value;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current_index >= self.length {
-            return None;
-        }
-
-        let mut value: napi_value = ptr::null_mut();
-        let status =
-            unsafe { napi_get_element(self.env, self.array, self.current_index, &mut value) };
-        debug_assert!(status == napi_status_napi_ok);
-
-        self.current_index += 1;
-
-        Some(value)
-    }
-}
+    check_status(env, status)?;
 
-impl ExactSizeIterator for NapiArray {
-    fn len(&self) -> usize {
-        self.length as usize
-    }
+    Ok(result)
 }
 
-pub fn get_object_map(env: napi_env, object: napi_value) -> BTreeMap<String, napi_value> {
+pub fn get_object_map(
+    env: napi_env,
+    object: napi_value,
+) -> Result<BTreeMap<String, napi_value>, NapiError> {
     //get keys of object.
     let mut map = BTreeMap::<String, napi_value>::new();
     let mut keys_value = ptr::null_mut();
     let status = unsafe { napi_get_property_names(env, object, &mut keys_value) };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    for key in NapiArray::from_existing(env, keys_value) {
+    for key in NapiArray::from_existing(env, keys_value)? {
+        let key = key?;
         let mut value: napi_value = ptr::null_mut();
         let status = unsafe { napi_get_property(env, object, key, &mut value) };
-        debug_assert!(status == napi_status_napi_ok);
+        check_status(env, status)?;
 
         if let Ok(key_string) = get_string(env, key) {
             map.insert(key_string, value);
         }
     }
 
-    map
+    Ok(map)
 }
 
-pub fn push_array(env: napi_env, array: napi_value, elem: napi_value) {
-    let mut return_value: napi_value = ptr::null_mut();
-    let mut push_fn: napi_value = ptr::null_mut();
-    let args: [napi_value; 1] = [elem];
-
-    let status = unsafe {
-        napi_get_named_property(env, array, "slice".as_ptr() as *const c_char, &mut push_fn)
-    };
-    debug_assert!(status == napi_status_napi_ok);
-
-    let status = unsafe {
-        napi_call_function(
-            env,
-            array,
-            push_fn,
-            1,
-            &args[0] as *const napi_value,
-            &mut return_value,
-        )
-    };
-
-    debug_assert!(status == napi_status_napi_ok);
-}
-
-pub fn slice_buffer(env: napi_env, buff: napi_value, beginning: usize, end: usize) -> napi_value {
+pub fn slice_buffer(
+    env: napi_env,
+    buff: napi_value,
+    beginning: usize,
+    end: usize,
+) -> Result<napi_value, NapiError> {
     let mut slice_fn: napi_value = ptr::null_mut();
     let mut args: [napi_value; 2] = [ptr::null_mut(), ptr::null_mut()];
     let mut return_value: napi_value = ptr::null_mut();
 
-    args[0] = create_int32(env, beginning as i32);
-    args[1] = create_int32(env, end as i32);
+    args[0] = create_int32(env, beginning as i32)?;
+    args[1] = create_int32(env, end as i32)?;
 
     let status = unsafe {
         napi_get_named_property(env, buff, "slice".as_ptr() as *const c_char, &mut slice_fn)
     };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
     let status = unsafe {
         napi_call_function(
@@ -397,9 +321,9 @@ pub fn slice_buffer(env: napi_env, buff: napi_value, beginning: usize, end: usiz
             &mut return_value,
         )
     };
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    return_value
+    Ok(return_value)
 }
 
 pub fn define_class(
@@ -408,7 +332,7 @@ pub fn define_class(
     constructor: napi_callback,
     data: *mut c_void,
     properties: &[napi_property_descriptor],
-) -> napi_value {
+) -> Result<napi_value, NapiError> {
     let mut result: napi_value = ptr::null_mut();
 
     let status = unsafe {
@@ -424,7 +348,7 @@ pub fn define_class(
         )
     };
 
-    debug_assert!(status == napi_status_napi_ok);
+    check_status(env, status)?;
 
-    result
+    Ok(result)
 }
@@ -0,0 +1,131 @@
+use napi_sys::*;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+
+use crate::napi::error::{check_status, NapiError};
+
+/// `napi_finalize` trampoline used by `wrap`. Reconstructs the `Box<T>` we
+/// handed to `napi_wrap` and drops it, running `T`'s destructor, once the JS
+/// object it was attached to is garbage collected.
+unsafe extern "C" fn finalize<T>(
+    _env: napi_env,
+    finalize_data: *mut c_void,
+    _finalize_hint: *mut c_void,
+) {
+    drop(Box::from_raw(finalize_data as *mut T));
+}
+
+/// Attach `data` to `js_object`'s `this`, retrievable later via `unwrap`.
+/// `data` is dropped automatically when `js_object` is garbage collected.
+///
+/// This is the standard pattern for exposing a stateful Rust value (an SSB
+/// feed handle, a db connection) as a JS class instance.
+pub fn wrap<T>(env: napi_env, js_object: napi_value, data: Box<T>) -> Result<(), NapiError> {
+    let data = Box::into_raw(data) as *mut c_void;
+
+    let status = unsafe {
+        napi_wrap(
+            env,
+            js_object,
+            data,
+            Some(finalize::<T>),
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+
+    check_status(env, status)
+}
+
+/// Retrieve the value previously attached to `js_object` via `wrap`.
+///
+/// # Safety
+/// The caller must ensure `T` matches the type that was passed to `wrap`
+/// for this object, and must choose `'a` such that the returned reference
+/// does not outlive `js_object` (it is only valid as long as the JS object
+/// is alive and keeps its wrapped data). Calling `unwrap` more than once
+/// for the same object while an earlier `&mut T` from it is still live is
+/// aliasing UB — both references point at the same wrapped data — so
+/// callers must ensure at most one outstanding `unwrap` borrow exists at a
+/// time for a given object.
+pub unsafe fn unwrap<'a, T>(env: napi_env, js_object: napi_value) -> Result<&'a mut T, NapiError> {
+    let mut result: *mut c_void = ptr::null_mut();
+
+    let status = napi_unwrap(env, js_object, &mut result);
+    check_status(env, status)?;
+
+    Ok(&mut *(result as *mut T))
+}
+
+/// Remove and return the value previously attached to `js_object` via
+/// `wrap`, taking ownership back from the JS object. The object's
+/// finalizer will no longer run for this value.
+///
+/// # Safety
+/// The caller must ensure `T` matches the type that was passed to `wrap`
+/// for this object.
+pub unsafe fn remove_wrap<T>(env: napi_env, js_object: napi_value) -> Result<Box<T>, NapiError> {
+    let mut result: *mut c_void = ptr::null_mut();
+
+    let status = napi_remove_wrap(env, js_object, &mut result);
+    check_status(env, status)?;
+
+    Ok(Box::from_raw(result as *mut T))
+}
+
+/// Set `object[key] = value` directly via `napi_set_named_property`,
+/// rather than reflecting through a JS-side method.
+pub fn set_named_property(
+    env: napi_env,
+    object: napi_value,
+    key: &str,
+    value: napi_value,
+) -> Result<(), NapiError> {
+    let key = CString::new(key).unwrap();
+    let status =
+        unsafe { napi_set_named_property(env, object, key.as_ptr() as *const c_char, value) };
+    check_status(env, status)
+}
+
+/// Read `object[key]` directly via `napi_get_named_property`.
+pub fn get_named_property(
+    env: napi_env,
+    object: napi_value,
+    key: &str,
+) -> Result<napi_value, NapiError> {
+    let key = CString::new(key).unwrap();
+    let mut value: napi_value = ptr::null_mut();
+
+    let status = unsafe {
+        napi_get_named_property(env, object, key.as_ptr() as *const c_char, &mut value)
+    };
+    check_status(env, status)?;
+
+    Ok(value)
+}
+
+/// Incrementally build a plain JS object by setting named properties,
+/// rather than constructing a whole `BTreeMap` and converting it at once.
+pub struct NapiObjectBuilder {
+    env: napi_env,
+    object: napi_value,
+}
+
+impl NapiObjectBuilder {
+    pub fn new(env: napi_env) -> Result<NapiObjectBuilder, NapiError> {
+        Ok(NapiObjectBuilder {
+            env,
+            object: crate::napi::create_object(env)?,
+        })
+    }
+
+    pub fn set(self, key: &str, value: napi_value) -> Result<NapiObjectBuilder, NapiError> {
+        set_named_property(self.env, self.object, key, value)?;
+        Ok(self)
+    }
+
+    pub fn build(self) -> napi_value {
+        self.object
+    }
+}
@@ -0,0 +1,129 @@
+use napi_sys::*;
+use std::os::raw::c_void;
+use std::ptr;
+
+use crate::napi::error::{check_status, NapiError};
+
+type FinalizeFn = Box<dyn FnOnce() + 'static>;
+
+/// `napi_finalize` trampoline used by `on_finalize`. Reconstructs and runs
+/// the boxed Rust closure once V8 actually collects the referenced value.
+unsafe extern "C" fn run_finalize(
+    _env: napi_env,
+    finalize_data: *mut c_void,
+    _finalize_hint: *mut c_void,
+) {
+    let finalize = Box::from_raw(finalize_data as *mut FinalizeFn);
+    (*finalize)();
+}
+
+/// A `napi_ref` wrapper that tracks whether it is currently strong (keeping
+/// its target alive) or weak (refcount 0, target may already be collected).
+///
+/// Mirrors the way `napi_reference_ref`/`napi_reference_unref` model
+/// strength as a plain refcount: raising it above zero makes the handle
+/// strong again, dropping it to zero makes it weak.
+pub struct NapiReference {
+    pub env: napi_env,
+    pub reference: napi_ref,
+    pub ref_count: u32,
+}
+
+impl NapiReference {
+    /// Create a reference to `value` with the given initial refcount. A
+    /// count of 0 creates a weak reference immediately.
+    pub fn reference(
+        env: napi_env,
+        value: napi_value,
+        initial_count: u32,
+    ) -> Result<NapiReference, NapiError> {
+        let mut reference: napi_ref = ptr::null_mut();
+
+        let status =
+            unsafe { napi_create_reference(env, value, initial_count, &mut reference) };
+        check_status(env, status)?;
+
+        Ok(NapiReference {
+            env,
+            reference,
+            ref_count: initial_count,
+        })
+    }
+
+    /// Register `finalize` (Rust-side cleanup, e.g. dropping state
+    /// associated with the referenced value) to run when V8 actually
+    /// collects the referenced value, via `napi_add_finalizer`. Unlike
+    /// dropping the `NapiReference` itself, this is genuinely tied to the
+    /// value's garbage collection.
+    ///
+    /// Fails if the target has already been collected (a weak reference
+    /// whose `get()` returns `None`), since there is nothing left to
+    /// attach a finalizer to.
+    pub fn on_finalize(&mut self, finalize: impl FnOnce() + 'static) -> Result<(), NapiError> {
+        let value = self.get()?.ok_or_else(|| {
+            NapiError::Message("reference target has already been collected".to_string())
+        })?;
+
+        let boxed: FinalizeFn = Box::new(finalize);
+        let data = Box::into_raw(Box::new(boxed)) as *mut c_void;
+
+        let status = unsafe {
+            napi_add_finalizer(
+                self.env,
+                value,
+                data,
+                Some(run_finalize),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        check_status(self.env, status)
+    }
+
+    /// Increment the refcount, making the reference strong if it was weak.
+    /// Returns the new refcount.
+    pub fn ref_up(&mut self) -> Result<u32, NapiError> {
+        let mut result = 0;
+        let status = unsafe { napi_reference_ref(self.env, self.reference, &mut result) };
+        check_status(self.env, status)?;
+
+        self.ref_count = result;
+        Ok(result)
+    }
+
+    /// Decrement the refcount. Returns the new refcount; 0 means the
+    /// reference is now weak and its target may be collected.
+    pub fn ref_down(&mut self) -> Result<u32, NapiError> {
+        let mut result = 0;
+        let status = unsafe { napi_reference_unref(self.env, self.reference, &mut result) };
+        check_status(self.env, status)?;
+
+        self.ref_count = result;
+        Ok(result)
+    }
+
+    /// Is this reference currently strong (refcount > 0)?
+    pub fn is_strong(&self) -> bool {
+        self.ref_count > 0
+    }
+
+    /// Get the referenced value, or `None` if this is a weak reference
+    /// whose target has already been garbage collected.
+    pub fn get(&self) -> Result<Option<napi_value>, NapiError> {
+        let mut value: napi_value = ptr::null_mut();
+        let status = unsafe { napi_get_reference_value(self.env, self.reference, &mut value) };
+        check_status(self.env, status)?;
+
+        if value.is_null() {
+            Ok(None)
+        } else {
+            Ok(Some(value))
+        }
+    }
+}
+
+impl Drop for NapiReference {
+    fn drop(&mut self) {
+        unsafe { napi_delete_reference(self.env, self.reference) };
+    }
+}
@@ -0,0 +1,577 @@
+//! A `serde` integration for moving whole Rust structs (e.g. SSB message
+//! types) across the FFI boundary in one call, instead of building them up
+//! field by field with `create_object`/`get_object_map`/`NapiArray`.
+
+use napi_sys::*;
+use serde::de::{self, DeserializeOwned, Visitor};
+use serde::ser::{self, Serialize};
+
+use crate::napi::error::{check_status, NapiError};
+use crate::napi::object::set_named_property;
+use crate::napi::{
+    check_is_buffer, create_buffer_copy, create_int32, create_string_utf8, get_buffer_info,
+    get_string, get_typeof, wrap_unsafe_create, wrap_unsafe_get, NapiArray,
+};
+
+impl ser::Error for NapiError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        NapiError::Message(msg.to_string())
+    }
+}
+
+impl de::Error for NapiError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        NapiError::Message(msg.to_string())
+    }
+}
+
+/// Convert any `T: Serialize` into a `napi_value`.
+pub fn to_napi<T: Serialize>(env: napi_env, value: &T) -> Result<napi_value, NapiError> {
+    value.serialize(Serializer { env })
+}
+
+/// Convert a `napi_value` into any `T: DeserializeOwned`.
+///
+/// Enums are not supported: `enum` is forwarded to `deserialize_any`, which
+/// never calls `visit_enum`, so deserializing an enum returns an error.
+pub fn from_napi<T: DeserializeOwned>(
+    env: napi_env,
+    value: napi_value,
+) -> Result<T, NapiError> {
+    T::deserialize(Deserializer { env, value })
+}
+
+pub struct Serializer {
+    env: napi_env,
+}
+
+pub struct SeqSerializer {
+    env: napi_env,
+    array: NapiArray,
+}
+
+pub struct MapSerializer {
+    env: napi_env,
+    object: napi_value,
+    pending_key: Option<String>,
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = napi_value;
+    type Error = NapiError;
+
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<napi_value, NapiError> {
+        wrap_unsafe_create(self.env, v, napi_get_boolean)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<napi_value, NapiError> {
+        self.serialize_i64(v as i64)
+    }
+    fn serialize_i16(self, v: i16) -> Result<napi_value, NapiError> {
+        self.serialize_i64(v as i64)
+    }
+    fn serialize_i32(self, v: i32) -> Result<napi_value, NapiError> {
+        create_int32(self.env, v)
+    }
+    fn serialize_i64(self, v: i64) -> Result<napi_value, NapiError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<napi_value, NapiError> {
+        self.serialize_f64(v as f64)
+    }
+    fn serialize_u16(self, v: u16) -> Result<napi_value, NapiError> {
+        self.serialize_f64(v as f64)
+    }
+    fn serialize_u32(self, v: u32) -> Result<napi_value, NapiError> {
+        self.serialize_f64(v as f64)
+    }
+    fn serialize_u64(self, v: u64) -> Result<napi_value, NapiError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<napi_value, NapiError> {
+        self.serialize_f64(v as f64)
+    }
+    fn serialize_f64(self, v: f64) -> Result<napi_value, NapiError> {
+        wrap_unsafe_create(self.env, v, napi_create_double)
+    }
+
+    fn serialize_char(self, v: char) -> Result<napi_value, NapiError> {
+        self.serialize_str(&v.to_string())
+    }
+    fn serialize_str(self, v: &str) -> Result<napi_value, NapiError> {
+        create_string_utf8(self.env, v)
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<napi_value, NapiError> {
+        create_buffer_copy(self.env, v)
+    }
+
+    fn serialize_none(self) -> Result<napi_value, NapiError> {
+        crate::napi::get_null_value(self.env)
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<napi_value, NapiError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<napi_value, NapiError> {
+        crate::napi::get_undefined_value(self.env)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<napi_value, NapiError> {
+        self.serialize_unit()
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<napi_value, NapiError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<napi_value, NapiError> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<napi_value, NapiError> {
+        let env = self.env;
+        let object = crate::napi::create_object(env)?;
+        let inner = to_napi(env, value)?;
+        set_named_property(env, object, variant, inner)?;
+        Ok(object)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, NapiError> {
+        Ok(SeqSerializer {
+            env: self.env,
+            array: NapiArray::with_capacity(self.env, len.unwrap_or(0))?,
+        })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, NapiError> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, NapiError> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, NapiError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer, NapiError> {
+        Ok(MapSerializer {
+            env: self.env,
+            object: crate::napi::create_object(self.env)?,
+            pending_key: None,
+        })
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer, NapiError> {
+        Ok(MapSerializer {
+            env: self.env,
+            object: crate::napi::create_object(self.env)?,
+            pending_key: None,
+        })
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer, NapiError> {
+        self.serialize_struct(_name, _len)
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = napi_value;
+    type Error = NapiError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), NapiError> {
+        let elem = to_napi(self.env, value)?;
+        self.array.push(elem)
+    }
+    fn end(self) -> Result<napi_value, NapiError> {
+        Ok(self.array.array)
+    }
+}
+
+macro_rules! impl_seq_like {
+    ($trait_name:ident, $method:ident) => {
+        impl ser::$trait_name for SeqSerializer {
+            type Ok = napi_value;
+            type Error = NapiError;
+
+            fn $method<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), NapiError> {
+                let elem = to_napi(self.env, value)?;
+                self.array.push(elem)
+            }
+            fn end(self) -> Result<napi_value, NapiError> {
+                Ok(self.array.array)
+            }
+        }
+    };
+}
+
+impl_seq_like!(SerializeTuple, serialize_element);
+impl_seq_like!(SerializeTupleStruct, serialize_field);
+impl_seq_like!(SerializeTupleVariant, serialize_field);
+
+/// Stringifies a primitive key the way `serde_json`'s map-key serializer
+/// does, instead of routing it through the full `Serializer` (which would
+/// produce a JS value, not a property name) and `get_string` (which
+/// hard-errors on anything that isn't already a JS string).
+struct MapKeySerializer;
+
+fn key_serializer_error() -> NapiError {
+    NapiError::Message("map keys must be a primitive type representable as a string".to_string())
+}
+
+impl ser::Serializer for MapKeySerializer {
+    type Ok = String;
+    type Error = NapiError;
+
+    type SerializeSeq = ser::Impossible<String, NapiError>;
+    type SerializeTuple = ser::Impossible<String, NapiError>;
+    type SerializeTupleStruct = ser::Impossible<String, NapiError>;
+    type SerializeTupleVariant = ser::Impossible<String, NapiError>;
+    type SerializeMap = ser::Impossible<String, NapiError>;
+    type SerializeStruct = ser::Impossible<String, NapiError>;
+    type SerializeStructVariant = ser::Impossible<String, NapiError>;
+
+    fn serialize_bool(self, v: bool) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_i8(self, v: i8) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_i16(self, v: i16) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_i32(self, v: i32) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_i64(self, v: i64) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_u8(self, v: u8) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_u16(self, v: u16) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_u32(self, v: u32) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_u64(self, v: u64) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_f32(self, v: f32) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_f64(self, v: f64) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_char(self, v: char) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_str(self, v: &str) -> Result<String, NapiError> {
+        Ok(v.to_string())
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, NapiError> {
+        Err(key_serializer_error())
+    }
+    fn serialize_none(self) -> Result<String, NapiError> {
+        Err(key_serializer_error())
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<String, NapiError> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<String, NapiError> {
+        Err(key_serializer_error())
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, NapiError> {
+        Err(key_serializer_error())
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, NapiError> {
+        Ok(variant.to_string())
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, NapiError> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, NapiError> {
+        Err(key_serializer_error())
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, NapiError> {
+        Err(key_serializer_error())
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, NapiError> {
+        Err(key_serializer_error())
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, NapiError> {
+        Err(key_serializer_error())
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, NapiError> {
+        Err(key_serializer_error())
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, NapiError> {
+        Err(key_serializer_error())
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, NapiError> {
+        Err(key_serializer_error())
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, NapiError> {
+        Err(key_serializer_error())
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = napi_value;
+    type Error = NapiError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), NapiError> {
+        self.pending_key = Some(key.serialize(MapKeySerializer)?);
+        Ok(())
+    }
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), NapiError> {
+        let key = self
+            .pending_key
+            .take()
+            .ok_or_else(|| NapiError::Message("serialize_value called before serialize_key".into()))?;
+        let value = to_napi(self.env, value)?;
+        set_named_property(self.env, self.object, &key, value)
+    }
+    fn end(self) -> Result<napi_value, NapiError> {
+        Ok(self.object)
+    }
+}
+
+macro_rules! impl_struct_like {
+    ($trait_name:ident) => {
+        impl ser::$trait_name for MapSerializer {
+            type Ok = napi_value;
+            type Error = NapiError;
+
+            fn serialize_field<T: ?Sized + Serialize>(
+                &mut self,
+                key: &'static str,
+                value: &T,
+            ) -> Result<(), NapiError> {
+                let value = to_napi(self.env, value)?;
+                set_named_property(self.env, self.object, key, value)
+            }
+            fn end(self) -> Result<napi_value, NapiError> {
+                Ok(self.object)
+            }
+        }
+    };
+}
+
+impl_struct_like!(SerializeStruct);
+impl_struct_like!(SerializeStructVariant);
+
+pub struct Deserializer {
+    env: napi_env,
+    value: napi_value,
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = NapiError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, NapiError> {
+        let env = self.env;
+        let value = self.value;
+
+        match get_typeof(env, value)? {
+            napi_valuetype_napi_undefined | napi_valuetype_napi_null => visitor.visit_unit(),
+            napi_valuetype_napi_boolean => {
+                let b = wrap_unsafe_get(env, value, napi_get_value_bool)?;
+                visitor.visit_bool(b)
+            }
+            napi_valuetype_napi_number => {
+                let mut n: f64 = 0.0;
+                let status = unsafe { napi_get_value_double(env, value, &mut n) };
+                check_status(env, status)?;
+                // Integral numbers go through `visit_i64` so integer targets
+                // accept them; float targets accept `visit_i64` as well.
+                if n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64 {
+                    visitor.visit_i64(n as i64)
+                } else {
+                    visitor.visit_f64(n)
+                }
+            }
+            napi_valuetype_napi_string => visitor.visit_string(get_string(env, value)?),
+            napi_valuetype_napi_object => {
+                if check_is_buffer(env, value)? {
+                    let (ptr, len) = get_buffer_info(env, value)?;
+                    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec();
+                    return visitor.visit_byte_buf(bytes);
+                }
+
+                let mut is_array = false;
+                let status = unsafe { napi_is_array(env, value, &mut is_array) };
+                check_status(env, status)?;
+
+                if is_array {
+                    visitor.visit_seq(SeqAccess {
+                        env,
+                        array: NapiArray::from_existing(env, value)?,
+                    })
+                } else {
+                    visitor.visit_map(MapAccess {
+                        env,
+                        entries: crate::napi::get_object_map(env, value)?.into_iter(),
+                        pending_value: None,
+                    })
+                }
+            }
+            other => Err(NapiError::Message(format!(
+                "unsupported napi_valuetype for deserialization: {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, NapiError> {
+        match get_typeof(self.env, self.value)? {
+            napi_valuetype_napi_undefined | napi_valuetype_napi_null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct SeqAccess {
+    env: napi_env,
+    array: NapiArray,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess {
+    type Error = NapiError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, NapiError> {
+        match self.array.next() {
+            Some(elem) => {
+                let elem = elem?;
+                seed.deserialize(Deserializer {
+                    env: self.env,
+                    value: elem,
+                })
+                .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+struct MapAccess {
+    env: napi_env,
+    entries: std::collections::btree_map::IntoIter<String, napi_value>,
+    pending_value: Option<napi_value>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = NapiError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, NapiError> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.pending_value = Some(value);
+                let key_value = create_string_utf8(self.env, &key)?;
+                seed.deserialize(Deserializer {
+                    env: self.env,
+                    value: key_value,
+                })
+                .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, NapiError> {
+        let value = self
+            .pending_value
+            .take()
+            .ok_or_else(|| NapiError::Message("next_value called before next_key".into()))?;
+        seed.deserialize(Deserializer {
+            env: self.env,
+            value,
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use napi_sys::*;
+use std::os::raw::c_void;
+use std::ptr;
+
+use crate::napi::error::{check_status, check_status_threadsafe, NapiError};
+
+/// Whether `NapiThreadsafeFunction::call` blocks the calling thread when
+/// the queue is full, or returns immediately with `napi_queue_full`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ThreadsafeFunctionCallMode {
+    Blocking,
+    NonBlocking,
+}
+
+impl From<ThreadsafeFunctionCallMode> for napi_threadsafe_function_call_mode {
+    fn from(mode: ThreadsafeFunctionCallMode) -> Self {
+        match mode {
+            ThreadsafeFunctionCallMode::Blocking => {
+                napi_threadsafe_function_call_mode_napi_tsfn_blocking
+            }
+            ThreadsafeFunctionCallMode::NonBlocking => {
+                napi_threadsafe_function_call_mode_napi_tsfn_nonblocking
+            }
+        }
+    }
+}
+
+/// Whether releasing a handle also aborts any calls still queued for the
+/// underlying threadsafe function.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ThreadsafeFunctionReleaseMode {
+    Release,
+    Abort,
+}
+
+impl From<ThreadsafeFunctionReleaseMode> for napi_threadsafe_function_release_mode {
+    fn from(mode: ThreadsafeFunctionReleaseMode) -> Self {
+        match mode {
+            ThreadsafeFunctionReleaseMode::Release => {
+                napi_threadsafe_function_release_mode_napi_tsfn_release
+            }
+            ThreadsafeFunctionReleaseMode::Abort => {
+                napi_threadsafe_function_release_mode_napi_tsfn_abort
+            }
+        }
+    }
+}
+
+/// A safe channel for calling back into JS from any thread, including
+/// libuv worker threads running SSB feed verification or db scans.
+///
+/// `T` is queued on `call` and handed to the user-supplied `call_js`
+/// trampoline on the JS main thread, which is responsible for converting
+/// it into `napi_value`s and invoking the wrapped JS callback.
+pub struct NapiThreadsafeFunction<T> {
+    env: napi_env,
+    tsfn: napi_threadsafe_function,
+    _marker: std::marker::PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for NapiThreadsafeFunction<T> {}
+unsafe impl<T: Send> Sync for NapiThreadsafeFunction<T> {}
+
+pub fn create_threadsafe_function<T>(
+    env: napi_env,
+    js_callback: napi_value,
+    max_queue_size: usize,
+    call_js: unsafe extern "C" fn(napi_env, napi_value, *mut c_void, *mut c_void),
+) -> Result<NapiThreadsafeFunction<T>, NapiError> {
+    let resource_name = crate::napi::create_string_utf8(env, "napi-threadsafe-function")?;
+    let mut tsfn: napi_threadsafe_function = ptr::null_mut();
+
+    let status = unsafe {
+        napi_create_threadsafe_function(
+            env,
+            js_callback,
+            ptr::null_mut(),
+            resource_name,
+            max_queue_size,
+            1, // initial_thread_count: this handle itself counts as one reference
+            ptr::null_mut(),
+            None,
+            ptr::null_mut(),
+            Some(call_js),
+            &mut tsfn,
+        )
+    };
+    check_status(env, status)?;
+
+    Ok(NapiThreadsafeFunction {
+        env,
+        tsfn,
+        _marker: std::marker::PhantomData,
+    })
+}
+
+impl<T> NapiThreadsafeFunction<T> {
+    /// Queue `value` for delivery to the JS callback on the main thread.
+    /// Callable from any thread.
+    ///
+    /// On failure this does not consult `napi_env` (it may not belong to
+    /// the calling thread), so the resulting error has no extended message
+    /// or engine error code — just the raw `napi_status`.
+    pub fn call(&self, value: T, mode: ThreadsafeFunctionCallMode) -> Result<(), NapiError> {
+        let data = Box::into_raw(Box::new(value)) as *mut c_void;
+
+        let status =
+            unsafe { napi_call_threadsafe_function(self.tsfn, data, mode.into()) };
+        check_status_threadsafe(status)
+    }
+
+    /// Acquire an additional reference to this threadsafe function, so
+    /// another thread can release its own handle independently. Callable
+    /// from any thread; see `call` for why errors carry no extended info.
+    pub fn acquire(&self) -> Result<(), NapiError> {
+        let status = unsafe { napi_acquire_threadsafe_function(self.tsfn) };
+        check_status_threadsafe(status)
+    }
+
+    /// Release a reference to this threadsafe function. Once the last
+    /// reference is released the underlying napi resource is torn down.
+    /// Callable from any thread; see `call` for why errors carry no
+    /// extended info.
+    pub fn release(&self, mode: ThreadsafeFunctionReleaseMode) -> Result<(), NapiError> {
+        let status =
+            unsafe { napi_release_threadsafe_function(self.tsfn, mode.into()) };
+        check_status_threadsafe(status)
+    }
+}